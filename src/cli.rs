@@ -0,0 +1,276 @@
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use k8s_openapi::api::apps::v1::{Deployment, DeploymentSpec};
+use k8s_openapi::api::core::v1::{Container, PodSpec, PodTemplateSpec, ServiceAccount};
+use k8s_openapi::api::rbac::v1::{ClusterRole, ClusterRoleBinding, PolicyRule, Role, RoleBinding, RoleRef, Subject};
+use k8s_openapi::apiextensions_apiserver::pkg::apis::apiextensions::v1::CustomResourceDefinition;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelector;
+use kube::{
+  api::{Api, DeleteParams, ObjectMeta, Patch, PatchParams},
+  Client, CustomResourceExt,
+};
+use std::collections::BTreeMap;
+
+use operator::{MapAccount, MapRole, MapUser};
+
+const APP_NAME: &str = "aws-auth-operator";
+const FIELD_MANAGER: &str = "aws-auth-operator";
+const AWS_AUTH_NAMESPACE: &str = "kube-system";
+const DEFAULT_IMAGE: &str = "ghcr.io/controlant-org/aws-auth-operator:latest";
+
+#[derive(Parser)]
+#[command(name = "aws-auth-operator", about = "Manage the kube-system/aws-auth ConfigMap from MapRole/MapUser/MapAccount objects")]
+pub struct Cli {
+  #[command(subcommand)]
+  pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+  /// Print the MapRole/MapUser/MapAccount CRD YAML to stdout
+  Crd,
+  /// Server-side-apply the CRDs, RBAC and Deployment needed to run the operator in-cluster
+  Install {
+    /// Image the Deployment should run
+    #[arg(long, default_value = DEFAULT_IMAGE)]
+    image: String,
+    /// Namespace to install the ServiceAccount and Deployment into
+    #[arg(long, default_value = "kube-system")]
+    namespace: String,
+  },
+  /// Delete everything created by `install`
+  Uninstall {
+    /// Namespace the ServiceAccount and Deployment were installed into
+    #[arg(long, default_value = "kube-system")]
+    namespace: String,
+  },
+  /// Run the controller loop (default when no subcommand is given)
+  Run,
+}
+
+pub fn crd_yaml() -> String {
+  [
+    serde_yaml::to_string(&MapRole::crd()).unwrap(),
+    serde_yaml::to_string(&MapUser::crd()).unwrap(),
+    serde_yaml::to_string(&MapAccount::crd()).unwrap(),
+  ]
+  .join("---\n")
+}
+
+fn labels() -> BTreeMap<String, String> {
+  BTreeMap::from([("app.kubernetes.io/name".to_string(), APP_NAME.to_string())])
+}
+
+fn service_account(namespace: &str) -> ServiceAccount {
+  ServiceAccount {
+    metadata: ObjectMeta {
+      name: Some(APP_NAME.to_string()),
+      namespace: Some(namespace.to_string()),
+      labels: Some(labels()),
+      ..Default::default()
+    },
+    ..Default::default()
+  }
+}
+
+/// `list`/`watch`/`deletecollection` carry no object name, so RBAC can't scope them by
+/// `resourceNames` the way it can `get`/`patch`/`delete` — granting them here would mean
+/// watching every ConfigMap in `kube-system`, not just `aws-auth`. Namespaced `Role` instead
+/// of a cluster-wide grant, since the operator only ever touches `aws-auth` in `kube-system`.
+fn configmap_role() -> Role {
+  Role {
+    metadata: ObjectMeta {
+      name: Some(APP_NAME.to_string()),
+      namespace: Some(AWS_AUTH_NAMESPACE.to_string()),
+      labels: Some(labels()),
+      ..Default::default()
+    },
+    rules: Some(vec![
+      PolicyRule {
+        api_groups: Some(vec!["".to_string()]),
+        resources: Some(vec!["configmaps".to_string()]),
+        resource_names: Some(vec!["aws-auth".to_string()]),
+        verbs: vec!["get".to_string(), "patch".to_string()],
+        ..Default::default()
+      },
+      PolicyRule {
+        api_groups: Some(vec!["".to_string()]),
+        resources: Some(vec!["configmaps".to_string()]),
+        verbs: vec!["list".to_string(), "watch".to_string()],
+        ..Default::default()
+      },
+    ]),
+  }
+}
+
+fn configmap_role_binding(namespace: &str) -> RoleBinding {
+  RoleBinding {
+    metadata: ObjectMeta {
+      name: Some(APP_NAME.to_string()),
+      namespace: Some(AWS_AUTH_NAMESPACE.to_string()),
+      labels: Some(labels()),
+      ..Default::default()
+    },
+    role_ref: RoleRef {
+      api_group: "rbac.authorization.k8s.io".to_string(),
+      kind: "Role".to_string(),
+      name: APP_NAME.to_string(),
+    },
+    subjects: Some(vec![Subject {
+      kind: "ServiceAccount".to_string(),
+      name: APP_NAME.to_string(),
+      namespace: Some(namespace.to_string()),
+      ..Default::default()
+    }]),
+  }
+}
+
+fn cluster_role() -> ClusterRole {
+  ClusterRole {
+    metadata: ObjectMeta {
+      name: Some(APP_NAME.to_string()),
+      labels: Some(labels()),
+      ..Default::default()
+    },
+    rules: Some(vec![
+      PolicyRule {
+        api_groups: Some(vec!["aws-auth.controlant.com".to_string()]),
+        resources: Some(vec!["maproles".to_string(), "mapusers".to_string(), "mapaccounts".to_string()]),
+        verbs: vec!["get".to_string(), "list".to_string(), "watch".to_string()],
+        ..Default::default()
+      },
+      PolicyRule {
+        api_groups: Some(vec!["aws-auth.controlant.com".to_string()]),
+        resources: Some(vec![
+          "maproles/finalizers".to_string(),
+          "mapusers/finalizers".to_string(),
+          "mapaccounts/finalizers".to_string(),
+        ]),
+        verbs: vec!["update".to_string()],
+        ..Default::default()
+      },
+      PolicyRule {
+        api_groups: Some(vec!["aws-auth.controlant.com".to_string()]),
+        resources: Some(vec!["maproles/status".to_string()]),
+        verbs: vec!["get".to_string(), "patch".to_string()],
+        ..Default::default()
+      },
+    ]),
+    ..Default::default()
+  }
+}
+
+fn cluster_role_binding(namespace: &str) -> ClusterRoleBinding {
+  ClusterRoleBinding {
+    metadata: ObjectMeta {
+      name: Some(APP_NAME.to_string()),
+      labels: Some(labels()),
+      ..Default::default()
+    },
+    role_ref: RoleRef {
+      api_group: "rbac.authorization.k8s.io".to_string(),
+      kind: "ClusterRole".to_string(),
+      name: APP_NAME.to_string(),
+    },
+    subjects: Some(vec![Subject {
+      kind: "ServiceAccount".to_string(),
+      name: APP_NAME.to_string(),
+      namespace: Some(namespace.to_string()),
+      ..Default::default()
+    }]),
+  }
+}
+
+fn deployment(namespace: &str, image: &str) -> Deployment {
+  Deployment {
+    metadata: ObjectMeta {
+      name: Some(APP_NAME.to_string()),
+      namespace: Some(namespace.to_string()),
+      labels: Some(labels()),
+      ..Default::default()
+    },
+    spec: Some(DeploymentSpec {
+      replicas: Some(1),
+      selector: LabelSelector {
+        match_labels: Some(labels()),
+        ..Default::default()
+      },
+      template: PodTemplateSpec {
+        metadata: Some(ObjectMeta {
+          labels: Some(labels()),
+          ..Default::default()
+        }),
+        spec: Some(PodSpec {
+          service_account_name: Some(APP_NAME.to_string()),
+          containers: vec![Container {
+            name: APP_NAME.to_string(),
+            image: Some(image.to_string()),
+            args: Some(vec!["run".to_string()]),
+            ..Default::default()
+          }],
+          ..Default::default()
+        }),
+      },
+      ..Default::default()
+    }),
+    ..Default::default()
+  }
+}
+
+/// Server-side-apply the CRDs, ServiceAccount, the `aws-auth` Role and RoleBinding, ClusterRole,
+/// ClusterRoleBinding and Deployment.
+pub async fn install(client: Client, image: &str, namespace: &str) -> Result<()> {
+  let params = PatchParams::apply(FIELD_MANAGER).force();
+
+  let crd_api = Api::<CustomResourceDefinition>::all(client.clone());
+  for crd in [MapRole::crd(), MapUser::crd(), MapAccount::crd()] {
+    let name = crd.metadata.name.clone().unwrap();
+    crd_api.patch(&name, &params, &Patch::Apply(&crd)).await?;
+  }
+
+  Api::<ServiceAccount>::namespaced(client.clone(), namespace)
+    .patch(APP_NAME, &params, &Patch::Apply(&service_account(namespace)))
+    .await?;
+
+  Api::<Role>::namespaced(client.clone(), AWS_AUTH_NAMESPACE)
+    .patch(APP_NAME, &params, &Patch::Apply(&configmap_role()))
+    .await?;
+
+  Api::<RoleBinding>::namespaced(client.clone(), AWS_AUTH_NAMESPACE)
+    .patch(APP_NAME, &params, &Patch::Apply(&configmap_role_binding(namespace)))
+    .await?;
+
+  Api::<ClusterRole>::all(client.clone())
+    .patch(APP_NAME, &params, &Patch::Apply(&cluster_role()))
+    .await?;
+
+  Api::<ClusterRoleBinding>::all(client.clone())
+    .patch(APP_NAME, &params, &Patch::Apply(&cluster_role_binding(namespace)))
+    .await?;
+
+  Api::<Deployment>::namespaced(client, namespace)
+    .patch(APP_NAME, &params, &Patch::Apply(&deployment(namespace, image)))
+    .await?;
+
+  Ok(())
+}
+
+/// Delete everything `install` created. Missing resources are ignored.
+pub async fn uninstall(client: Client, namespace: &str) -> Result<()> {
+  let dp = DeleteParams::default();
+
+  Api::<Deployment>::namespaced(client.clone(), namespace).delete(APP_NAME, &dp).await.ok();
+  Api::<ClusterRoleBinding>::all(client.clone()).delete(APP_NAME, &dp).await.ok();
+  Api::<ClusterRole>::all(client.clone()).delete(APP_NAME, &dp).await.ok();
+  Api::<RoleBinding>::namespaced(client.clone(), AWS_AUTH_NAMESPACE).delete(APP_NAME, &dp).await.ok();
+  Api::<Role>::namespaced(client.clone(), AWS_AUTH_NAMESPACE).delete(APP_NAME, &dp).await.ok();
+  Api::<ServiceAccount>::namespaced(client.clone(), namespace).delete(APP_NAME, &dp).await.ok();
+
+  let crd_api = Api::<CustomResourceDefinition>::all(client);
+  for crd in [MapRole::crd(), MapUser::crd(), MapAccount::crd()] {
+    let name = crd.metadata.name.clone().unwrap();
+    crd_api.delete(&name, &dp).await.ok();
+  }
+
+  Ok(())
+}
@@ -1,10 +1,18 @@
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::Condition;
 use kube::CustomResource;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 /// Map a role in AWS IAM to Kubernetes groups
-#[derive(CustomResource, Debug, Clone, Deserialize, Serialize, JsonSchema)]
-#[kube(group = "aws-auth.controlant.com", version = "v1", kind = "MapRole", namespaced)]
+#[derive(CustomResource, Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema)]
+#[kube(
+  group = "aws-auth.controlant.com",
+  version = "v1",
+  kind = "MapRole",
+  namespaced,
+  status = "MapRoleStatus",
+  printcolumn = r#"{"name":"Ready", "type":"string", "jsonPath":".status.conditions[?(@.type=='Ready')].status"}"#
+)]
 pub struct MapRoleSpec {
   /// ARN of the AWS Role
   pub rolearn: String,
@@ -13,3 +21,38 @@ pub struct MapRoleSpec {
   /// Groups in kube
   pub groups: Vec<String>,
 }
+
+/// Observed sync state of a `MapRole`, populated by the controller after each reconcile
+#[derive(Deserialize, Serialize, Clone, Debug, Default, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MapRoleStatus {
+  /// Standard Kubernetes conditions; currently just `Ready`
+  #[serde(default)]
+  pub conditions: Vec<Condition>,
+  /// `.metadata.generation` that `conditions` was last computed from
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub observed_generation: Option<i64>,
+  /// RFC 3339 timestamp of the last successful sync into `aws-auth`
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub synced_at: Option<String>,
+}
+
+/// Map an IAM user to a kube username and groups
+#[derive(CustomResource, Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema)]
+#[kube(group = "aws-auth.controlant.com", version = "v1", kind = "MapUser", namespaced)]
+pub struct MapUserSpec {
+  /// ARN of the AWS User
+  pub userarn: String,
+  /// Username inside kube
+  pub username: String,
+  /// Groups in kube
+  pub groups: Vec<String>,
+}
+
+/// Auto-permit every IAM principal in an AWS account
+#[derive(CustomResource, Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[kube(group = "aws-auth.controlant.com", version = "v1", kind = "MapAccount", namespaced)]
+pub struct MapAccountSpec {
+  /// AWS account ID to auto-permit
+  pub accountid: String,
+}
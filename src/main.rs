@@ -1,31 +1,31 @@
 use anyhow::{bail, Context as _};
 use futures_util::StreamExt;
-use json_patch::{PatchOperation, ReplaceOperation, TestOperation};
+use json_patch::{AddOperation, PatchOperation, ReplaceOperation, TestOperation};
 use k8s_openapi::api::core::v1::ConfigMap;
 use kube::{
   api::{Api, ListParams, Patch, PatchParams},
   runtime::{
-    controller::{self, Context, Controller, ReconcilerAction},
-    finalizer,
+    controller::{self, Action, Controller},
+    finalizer, watcher, WatchStreamExt,
   },
-  Client, CustomResource, CustomResourceExt, Resource,
+  Client, Resource,
 };
-use log::{debug, error, info};
-use schemars::JsonSchema;
-use serde::{Deserialize, Serialize};
+use log::{debug, error, info, warn};
+use rand::Rng;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{future::Future, sync::Arc, time::Duration};
 use thiserror::Error;
+use tokio::time::sleep;
 
-/// Map a role in AWS IAM to Kubernetes groups
-#[derive(CustomResource, Debug, Clone, Deserialize, Serialize, JsonSchema)]
-#[kube(group = "aws-auth.controlant.com", version = "v1", kind = "MapRole", namespaced)]
-pub struct MapRoleSpec {
-  /// ARN of the AWS Role
-  rolearn: String,
-  /// Username inside kube
-  username: String,
-  /// Groups in kube
-  groups: Vec<String>,
-}
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{Condition, Time};
+use k8s_openapi::chrono::Utc;
+use operator::{MapAccount, MapRole, MapRoleSpec, MapRoleStatus, MapUser, MapUserSpec};
+use serde_json::json;
+
+mod cli;
+mod metrics;
+
+const DEFAULT_METRICS_PORT: u16 = 8080;
 
 #[derive(Debug, Error)]
 enum AppError {
@@ -33,17 +33,102 @@ enum AppError {
   KubeError(#[from] kube::Error),
   #[error("Yaml decode error: {0:?}")]
   YamlError(#[from] serde_yaml::Error),
+  #[error("Gave up patching ConfigMap key {1:?} after {0} attempts due to concurrent writers")]
+  PatchConflictExhausted(u32, String),
+}
+
+const CAS_MAX_ATTEMPTS: u32 = 5;
+const CAS_BASE_DELAY_MS: u64 = 50;
+const CAS_MAX_DELAY_MS: u64 = 2000;
+
+/// The API server answers a JSON patch whose `test` operation fails with 422, and a
+/// resourceVersion conflict with 409; both mean another writer got there first.
+fn is_patch_conflict(err: &kube::Error) -> bool {
+  matches!(err, kube::Error::Api(ae) if ae.code == 409 || ae.code == 422)
+}
+
+fn is_not_found(err: &kube::Error) -> bool {
+  matches!(err, kube::Error::Api(ae) if ae.code == 404)
+}
+
+/// Read-modify-test-replace a single `data` key of the `aws-auth` ConfigMap, retrying with
+/// exponential backoff and jitter when the test operation loses a race against a concurrent
+/// writer. `compute_desired` is handed the key's current value (defaulted if the key is absent)
+/// and is re-run from a fresh read on every attempt so it always patches against the latest
+/// server-side value. Returns whether the key's value actually changed, so callers can distinguish
+/// a no-op reconcile from one that just synced.
+async fn cas_replace_configmap_key<T, F, Fut>(api: &Api<ConfigMap>, key: &str, mut compute_desired: F) -> Result<bool, AppError>
+where
+  T: Serialize + DeserializeOwned + PartialEq + Clone + Default,
+  F: FnMut(T) -> Fut,
+  Fut: Future<Output = Result<T, AppError>>,
+{
+  for attempt in 0..CAS_MAX_ATTEMPTS {
+    let aws_auth_cm = api.get("aws-auth").await?;
+    metrics::record_configmap_read();
+    let current_str = aws_auth_cm.data.as_ref().and_then(|d| d.get(key)).map(String::as_str);
+    let current: T = match current_str {
+      Some(s) => serde_yaml::from_str(s)?,
+      None => T::default(),
+    };
+
+    let desired = compute_desired(current.clone()).await?;
+    if current == desired {
+      return Ok(false);
+    }
+
+    let patch = api
+      .patch(
+        "aws-auth",
+        &PatchParams::default(),
+        &upsert_configmap_key_patch(key, current_str, serde_yaml::to_string(&desired)?),
+      )
+      .await;
+
+    match patch {
+      Ok(_) => return Ok(true),
+      Err(e) if is_patch_conflict(&e) => {
+        metrics::PATCH_CONFLICT_RETRIES_TOTAL.inc();
+        let backoff_ms = CAS_BASE_DELAY_MS.saturating_mul(1 << attempt).min(CAS_MAX_DELAY_MS);
+        let jittered_ms = rand::thread_rng().gen_range(0..=backoff_ms);
+        warn!(
+          "ConfigMap key {:?} patch lost a race (attempt {}/{}), retrying in {}ms",
+          key,
+          attempt + 1,
+          CAS_MAX_ATTEMPTS,
+          jittered_ms
+        );
+        sleep(Duration::from_millis(jittered_ms)).await;
+      }
+      Err(e) => return Err(e.into()),
+    }
+  }
+
+  Err(AppError::PatchConflictExhausted(CAS_MAX_ATTEMPTS, key.to_string()))
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
   env_logger::init();
 
-  println!("{}", serde_yaml::to_string(&MapRole::crd()).unwrap());
+  use clap::Parser;
+  let args = cli::Cli::parse();
 
+  match args.command.unwrap_or(cli::Command::Run) {
+    cli::Command::Crd => {
+      print!("{}", cli::crd_yaml());
+      Ok(())
+    }
+    cli::Command::Install { image, namespace } => cli::install(build_client().await?, &image, &namespace).await,
+    cli::Command::Uninstall { namespace } => cli::uninstall(build_client().await?, &namespace).await,
+    cli::Command::Run => run(build_client().await?).await,
+  }
+}
+
+async fn build_client() -> anyhow::Result<Client> {
   // try load from env var which Terraform uses
-  let client = match Client::try_default().await {
-    Ok(c) => c,
+  match Client::try_default().await {
+    Ok(c) => Ok(c),
     _ => {
       use kube::config::KubeConfigOptions;
       use std::convert::TryFrom;
@@ -56,135 +141,393 @@ async fn main() -> anyhow::Result<()> {
           })
           .await?,
         )
-        .context("Failed to load KUBE_CTX context")?,
+        .context("Failed to load KUBE_CTX context"),
 
         _ => bail!("Failed to create client"),
       }
     }
-  };
+  }
+}
+
+async fn run(client: Client) -> anyhow::Result<()> {
+  let metrics_port: u16 = std::env::var("METRICS_PORT")
+    .ok()
+    .and_then(|p| p.parse().ok())
+    .unwrap_or(DEFAULT_METRICS_PORT);
+
+  // The healthz/readyz/metrics server is a sidecar: if it fails to bind or dies, that should
+  // never take the controllers down with it, so it runs as its own task rather than racing
+  // alongside `controllers` below.
+  tokio::spawn(async move {
+    if let Err(e) = metrics::run(metrics_port).await {
+      error!(
+        "Metrics server exited, healthz/readyz/metrics are no longer served: {:?}",
+        e
+      );
+    }
+  });
+
+  futures::future::join3(
+    run_maprole_controller(client.clone()),
+    run_mapuser_controller(client.clone()),
+    run_mapaccount_controller(client.clone()),
+  )
+  .await;
 
-  // MAYBE: apply CRD
+  Ok(())
+}
 
+async fn run_maprole_controller(client: Client) {
   let crd = Api::<MapRole>::all(client.clone());
+  let sys_api = Api::<ConfigMap>::namespaced(client.clone(), "kube-system");
+  let all_mr_api = Api::<MapRole>::all(client.clone());
 
-  //   reconcile_all_on
+  // Any change to the aws-auth ConfigMap (e.g. a manual edit or revert) should heal by
+  // re-deriving mapRoles from the full set of MapRole objects, not just the one that changed.
+  let cm_trigger = watcher(
+    Api::<ConfigMap>::namespaced(client.clone(), "kube-system"),
+    watcher::Config::default().fields("metadata.name=aws-auth"),
+  )
+  .applied_objects()
+  .filter_map(|res| async move {
+    match res {
+      Ok(_) => Some(()),
+      Err(e) => {
+        error!("aws-auth ConfigMap watch failed, drift healing may be delayed: {:?}", e);
+        None
+      }
+    }
+  });
 
   Controller::new(crd, ListParams::default())
+    .reconcile_all_on(cm_trigger)
     .run(
-      |maprole, ctx| {
+      move |maprole, ctx| {
         debug!("Reconcile for: {:?}", &maprole);
 
-        let client = ctx.get_ref().clone();
-        let namespace = maprole.meta().namespace.as_deref().unwrap();
-        let mr_api = Api::<MapRole>::namespaced(client.clone(), &namespace);
-        let sys_api = Api::<ConfigMap>::namespaced(client.clone(), "kube-system");
+        let mr_api = Api::<MapRole>::namespaced(ctx.as_ref().clone(), maprole.meta().namespace.as_deref().unwrap());
+        let all_mr_api = all_mr_api.clone();
+        let sys_api = sys_api.clone();
         async move {
           finalizer::finalizer(&mr_api, "aws-auth-operator.controlant.com", maprole, |ev| async {
             match ev {
-              finalizer::Event::Apply(mr) => apply(mr, &sys_api).await,
-              finalizer::Event::Cleanup(mr) => cleanup(mr, &sys_api).await,
+              finalizer::Event::Apply(mr) => apply_role(mr, &mr_api, &all_mr_api, &sys_api).await,
+              finalizer::Event::Cleanup(mr) => cleanup_role(mr, &all_mr_api, &sys_api).await,
             }
           })
           .await
         }
       },
-      |_, _| requeue(60),
-      Context::new(client),
+      |_, _| Action::requeue(Duration::from_secs(60)),
+      Arc::new(client),
     )
     .for_each(|res| async move {
       match res {
         Ok(o) => {
-          info!("Reconciled {:?}", o);
-        }
-        Err(controller::Error::ObjectNotFound(or)) => {
-          info!("Object not found: {:?}", or);
+          metrics::RECONCILES_TOTAL.with_label_values(&["MapRole"]).inc();
+          info!("Reconciled MapRole {:?}", o);
         }
+        Err(controller::Error::ObjectNotFound(or)) => info!("MapRole not found: {:?}", or),
         Err(e) => {
-          error!("Reconcile failed: {:?}", e);
+          metrics::RECONCILE_ERRORS_TOTAL.with_label_values(&["MapRole"]).inc();
+          error!("MapRole reconcile failed: {:?}", e);
         }
       }
     })
     .await;
-
-  Ok(())
 }
 
-async fn apply(mr: MapRole, api: &Api<ConfigMap>) -> Result<ReconcilerAction, AppError> {
-  let aws_auth_cm = api.get("aws-auth").await?;
-  let cm_maproles_str = aws_auth_cm.data.as_ref().unwrap().get("mapRoles").unwrap();
-  let mut cm_maproles: Vec<MapRoleSpec> = serde_yaml::from_str(cm_maproles_str)?;
+async fn run_mapuser_controller(client: Client) {
+  let crd = Api::<MapUser>::all(client.clone());
+  let sys_api = Api::<ConfigMap>::namespaced(client.clone(), "kube-system");
 
-  if let Some(mut entry) = cm_maproles.iter_mut().find(|e| e.rolearn == mr.spec.rolearn) {
-    if (entry.username != mr.spec.username) || (entry.groups != mr.spec.groups) {
-      // update existing entry
-      entry.username = mr.spec.username;
-      entry.groups = mr.spec.groups;
-    } else {
-      return Ok(requeue(300));
-    }
-  } else {
-    // add new entry
-    cm_maproles.push(mr.spec.clone());
-  }
+  Controller::new(crd, ListParams::default())
+    .run(
+      move |mapuser, ctx| {
+        debug!("Reconcile for: {:?}", &mapuser);
 
-  api
-    .patch(
-      "aws-auth",
-      &PatchParams::default(),
-      &Patch::<()>::Json(json_patch::Patch(vec![
-        PatchOperation::Test(TestOperation {
-          path: "/data/mapRoles".to_string(),
-          value: cm_maproles_str.clone().into(),
-        }),
-        PatchOperation::Replace(ReplaceOperation {
-          path: "/data/mapRoles".to_string(),
-          value: serde_yaml::to_string(&cm_maproles)?.into(),
-        }),
-      ])),
+        let mu_api = Api::<MapUser>::namespaced(ctx.as_ref().clone(), mapuser.meta().namespace.as_deref().unwrap());
+        let sys_api = sys_api.clone();
+        async move {
+          finalizer::finalizer(&mu_api, "aws-auth-operator.controlant.com", mapuser, |ev| async {
+            match ev {
+              finalizer::Event::Apply(mu) => apply_user(mu, &sys_api).await,
+              finalizer::Event::Cleanup(mu) => cleanup_user(mu, &sys_api).await,
+            }
+          })
+          .await
+        }
+      },
+      |_, _| Action::requeue(Duration::from_secs(60)),
+      Arc::new(client),
     )
-    .await?;
+    .for_each(|res| async move {
+      match res {
+        Ok(o) => {
+          metrics::RECONCILES_TOTAL.with_label_values(&["MapUser"]).inc();
+          info!("Reconciled MapUser {:?}", o);
+        }
+        Err(controller::Error::ObjectNotFound(or)) => info!("MapUser not found: {:?}", or),
+        Err(e) => {
+          metrics::RECONCILE_ERRORS_TOTAL.with_label_values(&["MapUser"]).inc();
+          error!("MapUser reconcile failed: {:?}", e);
+        }
+      }
+    })
+    .await;
+}
+
+async fn run_mapaccount_controller(client: Client) {
+  let crd = Api::<MapAccount>::all(client.clone());
+  let sys_api = Api::<ConfigMap>::namespaced(client.clone(), "kube-system");
 
-  Ok(requeue(300))
+  Controller::new(crd, ListParams::default())
+    .run(
+      move |mapaccount, ctx| {
+        debug!("Reconcile for: {:?}", &mapaccount);
+
+        let ma_api = Api::<MapAccount>::namespaced(ctx.as_ref().clone(), mapaccount.meta().namespace.as_deref().unwrap());
+        let sys_api = sys_api.clone();
+        async move {
+          finalizer::finalizer(&ma_api, "aws-auth-operator.controlant.com", mapaccount, |ev| async {
+            match ev {
+              finalizer::Event::Apply(ma) => apply_account(ma, &sys_api).await,
+              finalizer::Event::Cleanup(ma) => cleanup_account(ma, &sys_api).await,
+            }
+          })
+          .await
+        }
+      },
+      |_, _| Action::requeue(Duration::from_secs(60)),
+      Arc::new(client),
+    )
+    .for_each(|res| async move {
+      match res {
+        Ok(o) => {
+          metrics::RECONCILES_TOTAL.with_label_values(&["MapAccount"]).inc();
+          info!("Reconciled MapAccount {:?}", o);
+        }
+        Err(controller::Error::ObjectNotFound(or)) => info!("MapAccount not found: {:?}", or),
+        Err(e) => {
+          metrics::RECONCILE_ERRORS_TOTAL.with_label_values(&["MapAccount"]).inc();
+          error!("MapAccount reconcile failed: {:?}", e);
+        }
+      }
+    })
+    .await;
 }
 
-async fn cleanup(mr: MapRole, api: &Api<ConfigMap>) -> Result<ReconcilerAction, AppError> {
-  let aws_auth_cm = api.get("aws-auth").await?;
-  let cm_maproles_str = aws_auth_cm.data.as_ref().unwrap().get("mapRoles").unwrap();
-  let mut cm_maproles: Vec<MapRoleSpec> = serde_yaml::from_str(cm_maproles_str)?;
+/// Build the desired `mapRoles` entries from every `MapRole` object in the cluster, excluding
+/// any whose uid is in `exclude` (used by cleanup to drop the object being finalized).
+async fn desired_maproles(api: &Api<MapRole>, exclude: Option<&str>) -> Result<Vec<MapRoleSpec>, AppError> {
+  let desired: Vec<MapRoleSpec> = api
+    .list(&ListParams::default())
+    .await?
+    .into_iter()
+    .filter(|mr| mr.meta().uid.as_deref() != exclude)
+    .map(|mr| mr.spec)
+    .collect();
 
-  if let Some((idx, _)) = cm_maproles
-    .iter()
-    .enumerate()
-    .find(|(_, e)| e.rolearn == mr.spec.rolearn)
-  {
-    cm_maproles.remove(idx);
+  metrics::MANAGED_ENTRIES.with_label_values(&["MapRole"]).set(desired.len() as i64);
 
-    api
-      .patch(
-        "aws-auth",
-        &PatchParams::default(),
-        &Patch::<()>::Json(json_patch::Patch(vec![
-          PatchOperation::Test(TestOperation {
-            path: "/data/mapRoles".to_string(),
-            value: cm_maproles_str.clone().into(),
-          }),
-          PatchOperation::Replace(ReplaceOperation {
-            path: "/data/mapRoles".to_string(),
-            value: serde_yaml::to_string(&cm_maproles)?.into(),
-          }),
-        ])),
-      )
-      .await?;
+  Ok(desired)
+}
+
+/// Per k8s condition conventions, `lastTransitionTime` only moves when `status` actually flips,
+/// not on every reconcile that leaves it unchanged (e.g. a clean 300s requeue, or a ConfigMap
+/// watch fan-out that re-reconciles every MapRole without anything to fix).
+fn ready_condition(
+  status: bool,
+  reason: &str,
+  message: String,
+  observed_generation: Option<i64>,
+  previous: Option<&Condition>,
+) -> Condition {
+  let status = if status { "True" } else { "False" }.to_string();
+  let last_transition_time = match previous {
+    Some(prev) if prev.status == status => prev.last_transition_time.clone(),
+    _ => Time(Utc::now()),
+  };
+
+  Condition {
+    type_: "Ready".to_string(),
+    status,
+    reason: reason.to_string(),
+    message,
+    observed_generation,
+    last_transition_time,
   }
+}
 
-  Ok(requeue(0))
+fn ready_condition_for(result: &Result<(), AppError>, observed_generation: Option<i64>, previous: Option<&Condition>) -> Condition {
+  match result {
+    Ok(()) => ready_condition(
+      true,
+      "Synced",
+      "mapRoles reflects every MapRole object".to_string(),
+      observed_generation,
+      previous,
+    ),
+    Err(AppError::KubeError(e)) if is_not_found(e) => ready_condition(
+      false,
+      "ConfigMapNotFound",
+      "the aws-auth ConfigMap does not exist in kube-system".to_string(),
+      observed_generation,
+      previous,
+    ),
+    Err(AppError::KubeError(e)) => ready_condition(
+      false,
+      "ConfigMapUnavailable",
+      format!("failed to read or patch the aws-auth ConfigMap: {e}"),
+      observed_generation,
+      previous,
+    ),
+    Err(AppError::YamlError(e)) => ready_condition(
+      false,
+      "InvalidYaml",
+      format!("aws-auth mapRoles is not valid YAML: {e}"),
+      observed_generation,
+      previous,
+    ),
+    Err(AppError::PatchConflictExhausted(attempts, key)) => ready_condition(
+      false,
+      "PatchConflict",
+      format!("gave up patching {key:?} after {attempts} attempts due to concurrent writers"),
+      observed_generation,
+      previous,
+    ),
+  }
 }
 
-fn requeue(secs: u64) -> ReconcilerAction {
-  match secs {
-    0 => ReconcilerAction { requeue_after: None },
-    t => ReconcilerAction {
-      requeue_after: Some(std::time::Duration::from_secs(t)),
-    },
+async fn patch_role_status(mr_api: &Api<MapRole>, mr: &MapRole, result: &Result<(), AppError>) {
+  let Some(name) = mr.meta().name.as_deref() else {
+    return;
+  };
+  let observed_generation = mr.meta().generation;
+  let previous_ready = mr
+    .status
+    .as_ref()
+    .and_then(|s| s.conditions.iter().find(|c| c.type_ == "Ready"));
+
+  let status = MapRoleStatus {
+    conditions: vec![ready_condition_for(result, observed_generation, previous_ready)],
+    observed_generation,
+    synced_at: result.is_ok().then(|| Utc::now().to_rfc3339()),
+  };
+
+  if let Err(e) = mr_api
+    .patch_status(name, &PatchParams::default(), &Patch::Merge(json!({ "status": status })))
+    .await
+  {
+    warn!("Failed to patch status for MapRole {:?}: {:?}", name, e);
   }
 }
+
+async fn apply_role(
+  mr: Arc<MapRole>,
+  ns_mr_api: &Api<MapRole>,
+  mr_api: &Api<MapRole>,
+  sys_api: &Api<ConfigMap>,
+) -> Result<Action, AppError> {
+  let result = cas_replace_configmap_key(sys_api, "mapRoles", |_| desired_maproles(mr_api, None))
+    .await
+    .map(|_changed| ());
+  patch_role_status(ns_mr_api, &mr, &result).await;
+  result?;
+  Ok(Action::requeue(Duration::from_secs(300)))
+}
+
+async fn cleanup_role(mr: Arc<MapRole>, mr_api: &Api<MapRole>, sys_api: &Api<ConfigMap>) -> Result<Action, AppError> {
+  let uid = mr.meta().uid.clone();
+  cas_replace_configmap_key(sys_api, "mapRoles", |_| desired_maproles(mr_api, uid.as_deref())).await?;
+  Ok(Action::await_change())
+}
+
+/// A real `aws-auth` ConfigMap commonly only has `mapRoles` set, so `mapUsers`/`mapAccounts` may
+/// be entirely absent the first time this operator touches it. Build a JSON patch that creates
+/// the key if it's missing (`add`) or test-and-replaces it if it's already there, so the very
+/// first `MapUser`/`MapAccount` object doesn't require the key to pre-exist.
+fn upsert_configmap_key_patch(key: &str, current_str: Option<&str>, desired_str: String) -> Patch<()> {
+  let path = format!("/data/{key}");
+  let ops = match current_str {
+    Some(cur) => vec![
+      PatchOperation::Test(TestOperation {
+        path: path.clone(),
+        value: cur.into(),
+      }),
+      PatchOperation::Replace(ReplaceOperation {
+        path,
+        value: desired_str.into(),
+      }),
+    ],
+    None => vec![PatchOperation::Add(AddOperation {
+      path,
+      value: desired_str.into(),
+    })],
+  };
+  Patch::Json(json_patch::Patch(ops))
+}
+
+async fn apply_user(mu: Arc<MapUser>, api: &Api<ConfigMap>) -> Result<Action, AppError> {
+  let changed = cas_replace_configmap_key(api, "mapUsers", move |mut cm_mapusers: Vec<MapUserSpec>| {
+    let mu = mu.clone();
+    async move {
+      match cm_mapusers.iter_mut().find(|e| e.userarn == mu.spec.userarn) {
+        Some(entry) => {
+          entry.username = mu.spec.username.clone();
+          entry.groups = mu.spec.groups.clone();
+        }
+        None => cm_mapusers.push(mu.spec.clone()),
+      }
+      metrics::MANAGED_ENTRIES.with_label_values(&["MapUser"]).set(cm_mapusers.len() as i64);
+      Ok(cm_mapusers)
+    }
+  })
+  .await?;
+
+  Ok(Action::requeue(Duration::from_secs(if changed { 300 } else { 60 })))
+}
+
+async fn cleanup_user(mu: Arc<MapUser>, api: &Api<ConfigMap>) -> Result<Action, AppError> {
+  cas_replace_configmap_key(api, "mapUsers", move |mut cm_mapusers: Vec<MapUserSpec>| {
+    let mu = mu.clone();
+    async move {
+      cm_mapusers.retain(|e| e.userarn != mu.spec.userarn);
+      metrics::MANAGED_ENTRIES.with_label_values(&["MapUser"]).set(cm_mapusers.len() as i64);
+      Ok(cm_mapusers)
+    }
+  })
+  .await?;
+
+  Ok(Action::await_change())
+}
+
+async fn apply_account(ma: Arc<MapAccount>, api: &Api<ConfigMap>) -> Result<Action, AppError> {
+  let changed = cas_replace_configmap_key(api, "mapAccounts", move |mut cm_mapaccounts: Vec<String>| {
+    let ma = ma.clone();
+    async move {
+      if !cm_mapaccounts.iter().any(|id| id == &ma.spec.accountid) {
+        cm_mapaccounts.push(ma.spec.accountid.clone());
+      }
+      metrics::MANAGED_ENTRIES.with_label_values(&["MapAccount"]).set(cm_mapaccounts.len() as i64);
+      Ok(cm_mapaccounts)
+    }
+  })
+  .await?;
+
+  Ok(Action::requeue(Duration::from_secs(if changed { 300 } else { 60 })))
+}
+
+async fn cleanup_account(ma: Arc<MapAccount>, api: &Api<ConfigMap>) -> Result<Action, AppError> {
+  cas_replace_configmap_key(api, "mapAccounts", move |mut cm_mapaccounts: Vec<String>| {
+    let ma = ma.clone();
+    async move {
+      cm_mapaccounts.retain(|id| *id != ma.spec.accountid);
+      metrics::MANAGED_ENTRIES.with_label_values(&["MapAccount"]).set(cm_mapaccounts.len() as i64);
+      Ok(cm_mapaccounts)
+    }
+  })
+  .await?;
+
+  Ok(Action::await_change())
+}
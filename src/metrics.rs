@@ -0,0 +1,100 @@
+use actix_web::{web, App, HttpResponse, HttpServer, Responder};
+use once_cell::sync::Lazy;
+use prometheus::{Encoder, IntCounter, IntCounterVec, IntGaugeVec, Opts, Registry, TextEncoder};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How stale the last successful `aws-auth` ConfigMap read may be before `/readyz` fails.
+const READY_THRESHOLD_SECS: i64 = 120;
+
+static REGISTRY: Lazy<Registry> = Lazy::new(Registry::new);
+
+pub static RECONCILES_TOTAL: Lazy<IntCounterVec> = Lazy::new(|| {
+  let c = IntCounterVec::new(
+    Opts::new("aws_auth_operator_reconciles_total", "Total reconciles processed, by kind"),
+    &["kind"],
+  )
+  .unwrap();
+  REGISTRY.register(Box::new(c.clone())).unwrap();
+  c
+});
+
+pub static RECONCILE_ERRORS_TOTAL: Lazy<IntCounterVec> = Lazy::new(|| {
+  let c = IntCounterVec::new(
+    Opts::new("aws_auth_operator_reconcile_errors_total", "Total failed reconciles, by kind"),
+    &["kind"],
+  )
+  .unwrap();
+  REGISTRY.register(Box::new(c.clone())).unwrap();
+  c
+});
+
+pub static PATCH_CONFLICT_RETRIES_TOTAL: Lazy<IntCounter> = Lazy::new(|| {
+  let c = IntCounter::new(
+    "aws_auth_operator_patch_conflict_retries_total",
+    "Total ConfigMap patch retries caused by a concurrent writer",
+  )
+  .unwrap();
+  REGISTRY.register(Box::new(c.clone())).unwrap();
+  c
+});
+
+pub static MANAGED_ENTRIES: Lazy<IntGaugeVec> = Lazy::new(|| {
+  let g = IntGaugeVec::new(
+    Opts::new("aws_auth_operator_managed_entries", "Entries currently managed in aws-auth, by kind"),
+    &["kind"],
+  )
+  .unwrap();
+  REGISTRY.register(Box::new(g.clone())).unwrap();
+  g
+});
+
+static LAST_CONFIGMAP_READ_UNIX_SECS: AtomicI64 = AtomicI64::new(0);
+
+/// Record that the `aws-auth` ConfigMap was just read successfully, for `/readyz`.
+pub fn record_configmap_read() {
+  let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+  LAST_CONFIGMAP_READ_UNIX_SECS.store(now, Ordering::Relaxed);
+}
+
+fn configmap_read_is_fresh() -> bool {
+  let last = LAST_CONFIGMAP_READ_UNIX_SECS.load(Ordering::Relaxed);
+  if last == 0 {
+    return false;
+  }
+  let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+  now - last <= READY_THRESHOLD_SECS
+}
+
+async fn healthz() -> impl Responder {
+  HttpResponse::Ok().body("ok")
+}
+
+async fn readyz() -> impl Responder {
+  if configmap_read_is_fresh() {
+    HttpResponse::Ok().body("ok")
+  } else {
+    HttpResponse::ServiceUnavailable().body("aws-auth ConfigMap has not been read recently")
+  }
+}
+
+async fn metrics() -> impl Responder {
+  let encoder = TextEncoder::new();
+  let metric_families = REGISTRY.gather();
+  let mut buffer = Vec::new();
+  encoder.encode(&metric_families, &mut buffer).unwrap();
+  HttpResponse::Ok().content_type(encoder.format_type()).body(buffer)
+}
+
+/// Serve `/healthz`, `/readyz` and `/metrics` until the process exits.
+pub async fn run(port: u16) -> std::io::Result<()> {
+  HttpServer::new(|| {
+    App::new()
+      .route("/healthz", web::get().to(healthz))
+      .route("/readyz", web::get().to(readyz))
+      .route("/metrics", web::get().to(metrics))
+  })
+  .bind(("0.0.0.0", port))?
+  .run()
+  .await
+}